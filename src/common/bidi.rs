@@ -0,0 +1,225 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use async_std::sync::Mutex;
+use async_tungstenite::async_std::connect_async;
+use async_tungstenite::tungstenite::Message;
+use futures::channel::{mpsc, oneshot};
+use futures::{SinkExt, StreamExt};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::error::{WebDriverError, WebDriverResult};
+
+/// An outgoing BiDi command, encoded as a JSON-RPC style frame:
+/// `{"id": <u64>, "method": "<module>.<command>", "params": {...}}`.
+#[derive(Debug, Serialize)]
+struct BidiCommand {
+    id: u64,
+    method: String,
+    params: Value,
+}
+
+/// An incoming BiDi frame: the response to a command we sent, successful or
+/// not (matched by `id`), or an unsolicited event identified by `method`.
+///
+/// This is an untagged enum, so serde tries variants in declaration order:
+/// an error frame (`{"id", "error", "message"}`) matches `ErrorResponse`, a
+/// success frame (`{"id", "result"}`) has no `error` key and falls through
+/// to `CommandResponse`, and a frame with `method` and `params` but no `id`
+/// is read as an `Event`.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum BidiMessage {
+    ErrorResponse {
+        id: u64,
+        error: String,
+        #[serde(default)]
+        message: String,
+    },
+    CommandResponse {
+        id: u64,
+        result: Value,
+    },
+    Event {
+        method: String,
+        params: Value,
+    },
+}
+
+type PendingMap = Arc<Mutex<HashMap<u64, oneshot::Sender<WebDriverResult<Value>>>>>;
+type Subscribers = Arc<Mutex<Vec<mpsc::UnboundedSender<(String, Value)>>>>;
+
+/// A live connection to the remote end's WebDriver BiDi WebSocket, opened
+/// when the session was negotiated with the `webSocketUrl` capability.
+///
+/// Commands are sent as JSON-RPC-style frames and demultiplexed by `id`
+/// (a response whose `id` matches no pending command is dropped); frames
+/// without an `id` that carry `method` and `params` are treated as events and
+/// forwarded to every receiver handed out by [`BidiConnection::subscribe`],
+/// so callers can listen for things like `log.entryAdded` or
+/// `browsingContext.load` instead of polling for them.
+#[derive(Debug, Clone)]
+pub struct BidiConnection {
+    next_id: Arc<AtomicU64>,
+    pending: PendingMap,
+    subscribers: Subscribers,
+    outgoing: mpsc::UnboundedSender<Message>,
+}
+
+impl BidiConnection {
+    /// Connect to the given `webSocketUrl` and spawn the background tasks
+    /// that pump the socket and demultiplex responses/events.
+    pub async fn connect(websocket_url: &str) -> WebDriverResult<Self> {
+        let (ws_stream, _) = connect_async(websocket_url).await.map_err(|e| {
+            WebDriverError::FatalError(format!("failed to connect to BiDi websocket: {}", e))
+        })?;
+        let (mut sink, mut stream) = ws_stream.split();
+        let (outgoing_tx, mut outgoing_rx) = mpsc::unbounded();
+        let pending: PendingMap = Arc::new(Mutex::new(HashMap::new()));
+        let subscribers: Subscribers = Arc::new(Mutex::new(Vec::new()));
+
+        async_std::task::spawn(async move {
+            while let Some(frame) = outgoing_rx.next().await {
+                if sink.send(frame).await.is_err() {
+                    break;
+                }
+            }
+        });
+
+        let pending_read = pending.clone();
+        let subscribers_read = subscribers.clone();
+        async_std::task::spawn(async move {
+            // Only `Text` frames carry a BiDi payload. Ping/Pong/Binary frames
+            // are routine on a long-lived WebSocket and must not end the loop -
+            // only a `Close` frame, a transport error, or the stream ending
+            // (`None`) stops demultiplexing.
+            loop {
+                let text = match stream.next().await {
+                    Some(Ok(Message::Text(text))) => text,
+                    Some(Ok(Message::Close(_))) | None => break,
+                    // Ping/Pong/Binary frames carry no BiDi payload - keep reading.
+                    Some(Ok(_)) => continue,
+                    // The transport itself failed; the socket is no longer usable.
+                    Some(Err(_)) => break,
+                };
+
+                let message: BidiMessage = match serde_json::from_str(&text) {
+                    Ok(message) => message,
+                    Err(_) => continue,
+                };
+                match message {
+                    BidiMessage::CommandResponse {
+                        id,
+                        result,
+                    } => {
+                        if let Some(tx) = pending_read.lock().await.remove(&id) {
+                            let _ = tx.send(Ok(result));
+                        }
+                    }
+                    BidiMessage::ErrorResponse {
+                        id,
+                        error,
+                        message,
+                    } => {
+                        if let Some(tx) = pending_read.lock().await.remove(&id) {
+                            let _ = tx.send(Err(WebDriverError::FatalError(format!(
+                                "BiDi command failed: {} ({})",
+                                error, message
+                            ))));
+                        }
+                    }
+                    BidiMessage::Event {
+                        method,
+                        params,
+                    } => {
+                        let mut subs = subscribers_read.lock().await;
+                        subs.retain(|tx| tx.unbounded_send((method.clone(), params.clone())).is_ok());
+                    }
+                }
+            }
+        });
+
+        Ok(Self {
+            next_id: Arc::new(AtomicU64::new(1)),
+            pending,
+            subscribers,
+            outgoing: outgoing_tx,
+        })
+    }
+
+    /// Send a BiDi command and wait for its response.
+    pub async fn send(&self, method: &str, params: Value) -> WebDriverResult<Value> {
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        let (tx, rx) = oneshot::channel();
+        self.pending.lock().await.insert(id, tx);
+
+        let command = BidiCommand {
+            id,
+            method: method.to_owned(),
+            params,
+        };
+        let frame = Message::Text(serde_json::to_string(&command)?);
+        self.outgoing.unbounded_send(frame).map_err(|e| {
+            WebDriverError::FatalError(format!("BiDi connection closed: {}", e))
+        })?;
+
+        rx.await
+            .map_err(|_| WebDriverError::FatalError("BiDi connection closed before reply".to_string()))?
+    }
+
+    /// Subscribe to unsolicited events such as `log.entryAdded` or
+    /// `browsingContext.load`. The returned receiver yields every event the
+    /// remote end sends as `(method, params)`; filter on `method` if you
+    /// only care about one event type.
+    pub async fn subscribe(&self) -> mpsc::UnboundedReceiver<(String, Value)> {
+        let (tx, rx) = mpsc::unbounded();
+        self.subscribers.lock().await.push(tx);
+        rx
+    }
+}
+
+/// The BiDi-facing half of a WebDriver session, meant to be constructed from
+/// a NewSession response: [`Client::from_new_session_capabilities`] parses
+/// and retains the `webSocketUrl` capability, if the remote end negotiated
+/// one, and [`Client::bidi`] opens the connection lazily on first use rather
+/// than eagerly, since not every session that could use BiDi does.
+///
+/// Nothing in this crate constructs a `Client` yet - that's the session
+/// module's job once it exists - so this is the seam that wiring is expected
+/// to plug into, not something already in the session-creation path.
+#[derive(Debug)]
+pub struct Client {
+    websocket_url: Option<String>,
+    bidi: Mutex<Option<BidiConnection>>,
+}
+
+impl Client {
+    /// Parse and retain the `webSocketUrl` capability, if any, from the
+    /// `capabilities` object of a NewSession response.
+    pub fn from_new_session_capabilities(capabilities: &Value) -> Self {
+        let websocket_url =
+            capabilities.get("webSocketUrl").and_then(Value::as_str).map(str::to_owned);
+        Self {
+            websocket_url,
+            bidi: Mutex::new(None),
+        }
+    }
+
+    /// Get the live [`BidiConnection`], connecting on first use if the
+    /// session negotiated a `webSocketUrl`. Returns `None` if the remote end
+    /// didn't support BiDi, so there's nothing to connect to.
+    pub async fn bidi(&self) -> WebDriverResult<Option<BidiConnection>> {
+        let url = match self.websocket_url.as_deref() {
+            Some(url) => url,
+            None => return Ok(None),
+        };
+
+        let mut guard = self.bidi.lock().await;
+        if guard.is_none() {
+            *guard = Some(BidiConnection::connect(url).await?);
+        }
+        Ok(guard.clone())
+    }
+}
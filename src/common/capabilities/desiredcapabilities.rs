@@ -1,3 +1,4 @@
+use base64::Engine;
 use serde::Serialize;
 use serde_json::{json, to_value, Value};
 
@@ -7,7 +8,19 @@ use crate::common::capabilities::firefox::FirefoxCapabilities;
 use crate::common::capabilities::ie::InternetExplorerCapabilities;
 use crate::common::capabilities::opera::OperaCapabilities;
 use crate::common::capabilities::safari::SafariCapabilities;
-use crate::error::WebDriverResult;
+use crate::error::{WebDriverError, WebDriverResult};
+use url::Url;
+
+/// The largest integer a W3C timeout value may hold (2^53 - 1), per the
+/// `WebDriver` spec's definition of a "safe integer".
+const MAX_SAFE_INTEGER: u64 = 9_007_199_254_740_991;
+
+const PAGE_LOAD_STRATEGIES: &[&str] = &["none", "eager", "normal"];
+
+const UNHANDLED_PROMPT_BEHAVIORS: &[&str] =
+    &["dismiss", "accept", "dismiss and notify", "accept and notify", "ignore"];
+
+const PROXY_TYPES: &[&str] = &["pac", "direct", "autodetect", "system", "manual"];
 
 const W3C_CAPABILITY_NAMES: &[&str] = &[
     "acceptInsecureCerts",
@@ -20,6 +33,7 @@ const W3C_CAPABILITY_NAMES: &[&str] = &[
     "timeouts",
     "unhandledPromptBehavior",
     "strictFileInteractability",
+    "webSocketUrl",
 ];
 
 const OSS_W3C_CONVERSION: &[(&str, &str)] = &[
@@ -28,28 +42,175 @@ const OSS_W3C_CONVERSION: &[(&str, &str)] = &[
     ("platform", "platformName"),
 ];
 
-pub fn make_w3c_caps(caps: &serde_json::Value) -> serde_json::Value {
-    let mut always_match = serde_json::json!({});
-
+/// Rewrites legacy OSS-style keys (`version`, `platform`, `acceptSslCerts`)
+/// to their W3C equivalents, drops anything that isn't a recognised W3C
+/// capability or vendor-prefixed key, then validates the result.
+///
+/// Validation has to run *after* normalization: `W3C_CAPABILITY_NAMES`
+/// deliberately excludes the legacy OSS keys (they're only valid pre-rename),
+/// so validating the raw input would reject any capabilities built with the
+/// long-standing `Capabilities::set_version`/`set_platform`/`accept_ssl_certs`
+/// helpers.
+fn validate_and_normalize(caps: &Value) -> WebDriverResult<Value> {
+    let mut normalized = serde_json::json!({});
     if let Some(caps_map) = caps.as_object() {
         for (k, v) in caps_map.iter() {
             if !v.is_null() {
                 for (k_from, k_to) in OSS_W3C_CONVERSION {
                     if k_from == k {
-                        always_match[k_to] = v.clone();
+                        normalized[k_to] = v.clone();
                     }
                 }
             }
 
             if W3C_CAPABILITY_NAMES.contains(&k.as_str()) || k.contains(':') {
-                always_match[k] = v.clone();
+                normalized[k] = v.clone();
+            }
+        }
+    }
+
+    validate_w3c_caps(&normalized)?;
+    Ok(normalized)
+}
+
+/// Build the W3C NewSession payload: `caps` becomes `alwaysMatch`, and any
+/// `firstMatch` alternatives are included too. Both are validated against
+/// the W3C spec and normalized identically before being emitted, so a
+/// malformed or legacy-style capability is rejected or rewritten the same
+/// way no matter which one it came from.
+pub fn make_w3c_caps(caps: &serde_json::Value, first_match: &[Value]) -> WebDriverResult<serde_json::Value> {
+    let always_match = validate_and_normalize(caps)?;
+
+    // With no alternatives registered via `DesiredCapabilities::add_first_match`,
+    // fall back to a single empty entry so every constraint comes from `alwaysMatch`,
+    // matching the previous behaviour.
+    let first_match = if first_match.is_empty() {
+        vec![json!({})]
+    } else {
+        first_match.iter().map(validate_and_normalize).collect::<WebDriverResult<Vec<_>>>()?
+    };
+
+    Ok(json!({
+        "firstMatch": first_match, "alwaysMatch": always_match
+    }))
+}
+
+/// Validate a capabilities object against the W3C WebDriver spec, mirroring
+/// the checks the Mozilla webdriver crate performs before sending NewSession,
+/// so malformed capabilities are rejected locally with a clear error instead
+/// of an opaque one from the remote end.
+fn validate_w3c_caps(caps: &Value) -> WebDriverResult<()> {
+    let caps_map = match caps.as_object() {
+        Some(map) => map,
+        None => return Ok(()),
+    };
+
+    if let Some(timeouts) = caps_map.get("timeouts") {
+        validate_timeouts(timeouts)?;
+    }
+
+    if let Some(strategy) = caps_map.get("pageLoadStrategy") {
+        validate_page_load_strategy(strategy)?;
+    }
+
+    if let Some(behaviour) = caps_map.get("unhandledPromptBehavior") {
+        validate_unhandled_prompt_behavior(behaviour)?;
+    }
+
+    if let Some(proxy) = caps_map.get("proxy") {
+        validate_proxy(proxy)?;
+    }
+
+    for key in caps_map.keys() {
+        if !W3C_CAPABILITY_NAMES.contains(&key.as_str()) && !key.contains(':') {
+            return Err(WebDriverError::FatalError(format!(
+                "unknown capability '{}': top-level keys must be a recognised W3C capability \
+                 or use a vendor prefix such as 'moz:' or 'goog:'",
+                key
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+fn validate_timeouts(timeouts: &Value) -> WebDriverResult<()> {
+    let map = timeouts
+        .as_object()
+        .ok_or_else(|| WebDriverError::FatalError("'timeouts' must be an object".to_string()))?;
+
+    for key in ["script", "pageLoad", "implicit"] {
+        match map.get(key) {
+            None | Some(Value::Null) => {}
+            Some(Value::Number(n)) if matches!(n.as_u64(), Some(v) if v <= MAX_SAFE_INTEGER) => {}
+            _ => {
+                return Err(WebDriverError::FatalError(format!(
+                    "'timeouts.{}' must be a non-negative integer no larger than 2^53 - 1",
+                    key
+                )))
             }
         }
     }
 
-    json!({
-        "firstMatch": [{}], "alwaysMatch": always_match
-    })
+    Ok(())
+}
+
+fn validate_page_load_strategy(value: &Value) -> WebDriverResult<()> {
+    match value.as_str() {
+        Some(s) if PAGE_LOAD_STRATEGIES.contains(&s) => Ok(()),
+        _ => Err(WebDriverError::FatalError(format!(
+            "'pageLoadStrategy' must be one of {:?}, got {}",
+            PAGE_LOAD_STRATEGIES, value
+        ))),
+    }
+}
+
+fn validate_unhandled_prompt_behavior(value: &Value) -> WebDriverResult<()> {
+    match value.as_str() {
+        Some(s) if UNHANDLED_PROMPT_BEHAVIORS.contains(&s) => Ok(()),
+        _ => Err(WebDriverError::FatalError(format!(
+            "'unhandledPromptBehavior' must be one of {:?}, got {}",
+            UNHANDLED_PROMPT_BEHAVIORS, value
+        ))),
+    }
+}
+
+fn validate_proxy(value: &Value) -> WebDriverResult<()> {
+    let map = value
+        .as_object()
+        .ok_or_else(|| WebDriverError::FatalError("'proxy' must be an object".to_string()))?;
+
+    let proxy_type = map.get("proxyType").and_then(Value::as_str).map(str::to_lowercase);
+    match proxy_type.as_deref() {
+        Some(t) if PROXY_TYPES.contains(&t) => {}
+        _ => {
+            return Err(WebDriverError::FatalError(format!(
+                "'proxy.proxyType' must be one of {:?}",
+                PROXY_TYPES
+            )))
+        }
+    }
+
+    if let Some(autoconfig_url) = map.get("proxyAutoconfigUrl") {
+        let url = autoconfig_url.as_str().ok_or_else(|| {
+            WebDriverError::FatalError("'proxy.proxyAutoconfigUrl' must be a string".to_string())
+        })?;
+        Url::parse(url).map_err(|e| {
+            WebDriverError::FatalError(format!("'proxy.proxyAutoconfigUrl' is not a valid URL: {}", e))
+        })?;
+    }
+
+    for key in ["httpProxy", "sslProxy", "ftpProxy", "socksProxy"] {
+        // `Proxy::Manual` serializes unset entries as `null`; treat those as absent.
+        if let Some(host_value) = map.get(key).filter(|v| !v.is_null()) {
+            let host = host_value
+                .as_str()
+                .ok_or_else(|| WebDriverError::FatalError(format!("'proxy.{}' must be a string", key)))?;
+            validate_host_port(key, host)?;
+        }
+    }
+
+    Ok(())
 }
 
 /// Merge two serde_json::Value structs.
@@ -73,6 +234,11 @@ fn merge(a: &mut Value, b: Value) {
 #[serde(transparent)]
 pub struct DesiredCapabilities {
     capabilities: Value,
+    /// Alternative `firstMatch` entries, populated via `add_first_match()`.
+    /// Not part of the serialized representation - `make_w3c_caps` reads it
+    /// separately when building the NewSession payload.
+    #[serde(skip)]
+    first_match: Vec<Value>,
 }
 
 impl DesiredCapabilities {
@@ -83,6 +249,7 @@ impl DesiredCapabilities {
     pub fn new(capabilities: Value) -> Self {
         Self {
             capabilities,
+            first_match: Vec::new(),
         }
     }
 
@@ -115,6 +282,52 @@ impl DesiredCapabilities {
     pub fn safari() -> SafariCapabilities {
         SafariCapabilities::new()
     }
+
+    /// Request a bidirectional (WebDriver BiDi) session by setting the
+    /// boolean `webSocketUrl` capability. If the remote end supports BiDi,
+    /// the NewSession response will include a `webSocketUrl`, which can be
+    /// handed to
+    /// [`Client::from_new_session_capabilities`](crate::common::bidi::Client::from_new_session_capabilities)
+    /// to obtain a [`Client`](crate::common::bidi::Client) and, from there,
+    /// a [`BidiConnection`](crate::common::bidi::BidiConnection) via
+    /// [`Client::bidi()`](crate::common::bidi::Client::bidi).
+    pub fn enable_bidi(&mut self) -> WebDriverResult<()> {
+        self.add("webSocketUrl", true)
+    }
+
+    /// Validate these capabilities against the W3C WebDriver spec. This runs
+    /// automatically as part of [`make_w3c_caps`] when building the
+    /// NewSession payload, so most users won't need to call it directly -
+    /// it's exposed for validating capabilities earlier, e.g. right after
+    /// constructing them.
+    pub fn validate(&self) -> WebDriverResult<()> {
+        validate_and_normalize(&self.capabilities).map(|_| ())
+    }
+
+    /// Add an alternative `firstMatch` entry, e.g. to request "Chrome OR
+    /// Firefox, whichever is available" in a single session call. The W3C
+    /// matching algorithm tries each `firstMatch` entry in order, merged
+    /// with `alwaysMatch`, and uses the first one the remote end can
+    /// satisfy - so constraints shared by every alternative should still go
+    /// through `add`/`add_subkey` rather than being repeated here.
+    pub fn add_first_match(&mut self, caps: impl Capabilities) {
+        self.first_match.push(caps.get().clone());
+    }
+
+    /// The `firstMatch` alternatives registered via `add_first_match`, folded
+    /// into the NewSession payload by [`DesiredCapabilities::to_w3c_caps`].
+    pub(crate) fn first_match(&self) -> &[Value] {
+        &self.first_match
+    }
+
+    /// Build the W3C NewSession payload for these capabilities, folding in
+    /// any `firstMatch` alternatives registered via `add_first_match`. This
+    /// is a thin wrapper around [`make_w3c_caps`]; nothing in this crate
+    /// calls it yet, but it's the intended call site for session creation
+    /// once that exists.
+    pub fn to_w3c_caps(&self) -> WebDriverResult<Value> {
+        make_w3c_caps(&self.capabilities, &self.first_match)
+    }
 }
 
 /// Add generic Capabilities implementation. This can be used as a convenient way to
@@ -212,6 +425,30 @@ pub trait Capabilities {
         self.add("acceptSslCerts", enabled)
     }
 
+    /// Set whether the session should accept untrusted/self-signed TLS
+    /// certificates by default. This writes the W3C `acceptInsecureCerts`
+    /// key, which modern drivers honour, directly - the legacy
+    /// [`Capabilities::accept_ssl_certs`] writes `acceptSslCerts`, which is
+    /// only rewritten to `acceptInsecureCerts` when [`make_w3c_caps`] builds
+    /// the NewSession payload.
+    fn accept_insecure_certs(&mut self, enabled: bool) -> WebDriverResult<()> {
+        self.add("acceptInsecureCerts", enabled)
+    }
+
+    /// Attach a base64-encoded browser extension (e.g. a `.crx` or `.xpi`
+    /// file's bytes) under a vendor options key's `extensions` list, the
+    /// shape both `goog:chromeOptions` and `moz:firefoxOptions` use.
+    fn add_extension(&mut self, options_key: &str, extension_bytes: &[u8]) -> WebDriverResult<()> {
+        let encoded = base64::engine::general_purpose::STANDARD.encode(extension_bytes);
+        let v = self.get_mut();
+        if v[options_key]["extensions"].is_null() {
+            v[options_key]["extensions"] = json!([encoded]);
+        } else if let Some(extensions) = v[options_key]["extensions"].as_array_mut() {
+            extensions.push(Value::String(encoded));
+        }
+        Ok(())
+    }
+
     /// Set whether the session can rotate the current page's layout between portrait and landscape
     /// orientations. Only applies to mobile platforms.
     fn set_rotatable(&mut self, enabled: bool) -> WebDriverResult<()> {
@@ -225,6 +462,7 @@ pub trait Capabilities {
 
     /// Set the proxy to use.
     fn set_proxy(&mut self, proxy: Proxy) -> WebDriverResult<()> {
+        proxy.validate()?;
         self.add("proxy", proxy)
     }
 
@@ -262,7 +500,9 @@ pub enum Proxy {
         socks_proxy: Option<String>,
         socks_username: Option<String>,
         socks_password: Option<String>,
-        no_proxy: Option<String>,
+        /// Host patterns to bypass the proxy for. Serialized as a JSON array
+        /// per the W3C `noProxy` spec, rather than a delimited string.
+        no_proxy: Option<Vec<String>>,
     },
     #[serde(rename = "pac")]
     AutoConfig {
@@ -273,6 +513,176 @@ pub enum Proxy {
     System,
 }
 
+impl Proxy {
+    fn empty_manual() -> Self {
+        Proxy::Manual {
+            ftp_proxy: None,
+            http_proxy: None,
+            ssl_proxy: None,
+            socks_proxy: None,
+            socks_username: None,
+            socks_password: None,
+            no_proxy: None,
+        }
+    }
+
+    /// Build a `Manual` proxy configuration with only an HTTP proxy set.
+    pub fn manual_http(http_proxy: impl Into<String>) -> Self {
+        let mut proxy = Self::empty_manual();
+        if let Proxy::Manual {
+            http_proxy: slot,
+            ..
+        } = &mut proxy
+        {
+            *slot = Some(http_proxy.into());
+        }
+        proxy
+    }
+
+    /// Build a `Manual` proxy configuration with only an SSL (HTTPS) proxy set.
+    pub fn manual_ssl(ssl_proxy: impl Into<String>) -> Self {
+        let mut proxy = Self::empty_manual();
+        if let Proxy::Manual {
+            ssl_proxy: slot,
+            ..
+        } = &mut proxy
+        {
+            *slot = Some(ssl_proxy.into());
+        }
+        proxy
+    }
+
+    /// Build a `Manual` proxy configuration with only an FTP proxy set.
+    pub fn manual_ftp(ftp_proxy: impl Into<String>) -> Self {
+        let mut proxy = Self::empty_manual();
+        if let Proxy::Manual {
+            ftp_proxy: slot,
+            ..
+        } = &mut proxy
+        {
+            *slot = Some(ftp_proxy.into());
+        }
+        proxy
+    }
+
+    /// Build a `Manual` proxy configuration with only a SOCKS proxy set.
+    /// Attach credentials with [`Proxy::socks_auth`].
+    pub fn manual_socks(socks_proxy: impl Into<String>) -> Self {
+        let mut proxy = Self::empty_manual();
+        if let Proxy::Manual {
+            socks_proxy: slot,
+            ..
+        } = &mut proxy
+        {
+            *slot = Some(socks_proxy.into());
+        }
+        proxy
+    }
+
+    /// Add host patterns to bypass the proxy for (the W3C `noProxy` list).
+    /// No-op on variants other than `Manual`.
+    pub fn bypass(mut self, hosts: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        if let Proxy::Manual {
+            no_proxy,
+            ..
+        } = &mut self
+        {
+            no_proxy.get_or_insert_with(Vec::new).extend(hosts.into_iter().map(Into::into));
+        }
+        self
+    }
+
+    /// Attach SOCKS credentials. No-op on variants other than `Manual`.
+    /// `validate()` rejects credentials set without a `socks_proxy`.
+    pub fn socks_auth(mut self, username: impl Into<String>, password: impl Into<String>) -> Self {
+        if let Proxy::Manual {
+            socks_username,
+            socks_password,
+            ..
+        } = &mut self
+        {
+            *socks_username = Some(username.into());
+            *socks_password = Some(password.into());
+        }
+        self
+    }
+
+    /// Validate this proxy configuration: SOCKS credentials require a
+    /// `socks_proxy` to be set, every proxy entry must be a well-formed
+    /// `host:port`, and every `noProxy` entry must be a well-formed host
+    /// bypass pattern.
+    pub fn validate(&self) -> WebDriverResult<()> {
+        if let Proxy::Manual {
+            ftp_proxy,
+            http_proxy,
+            ssl_proxy,
+            socks_proxy,
+            socks_username,
+            socks_password,
+            no_proxy,
+        } = self
+        {
+            if (socks_username.is_some() || socks_password.is_some()) && socks_proxy.is_none() {
+                return Err(WebDriverError::FatalError(
+                    "proxy.socksUsername/socksPassword require proxy.socksProxy to be set".to_string(),
+                ));
+            }
+
+            for (name, value) in [
+                ("ftpProxy", ftp_proxy),
+                ("httpProxy", http_proxy),
+                ("sslProxy", ssl_proxy),
+                ("socksProxy", socks_proxy),
+            ] {
+                if let Some(host) = value {
+                    validate_host_port(name, host)?;
+                }
+            }
+
+            if let Some(hosts) = no_proxy {
+                for host in hosts {
+                    validate_no_proxy_entry(host)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn validate_host_port(key: &str, host: &str) -> WebDriverResult<()> {
+    let invalid = || {
+        WebDriverError::FatalError(format!("'proxy.{}' entry '{}' is not a valid host:port", key, host))
+    };
+
+    // Split on the last `:` rather than handing the whole thing to `Url::parse`
+    // under a throwaway scheme - that accepts a bare hostname (no port at all)
+    // and even an empty host, neither of which is a usable `host:port` pair.
+    let (hostname, port) = host.rsplit_once(':').ok_or_else(invalid)?;
+    if hostname.is_empty() {
+        return Err(invalid());
+    }
+    port.parse::<u16>().map_err(|_| invalid())?;
+    Url::parse(&format!("proxy://{}", host)).map_err(|_| invalid())?;
+    Ok(())
+}
+
+/// Validate a `proxy.noProxy` entry: a bypass *pattern*, not a `host:port`
+/// pair, per the W3C spec - e.g. `localhost`, `.example.com` (a domain and
+/// its subdomains), or `*.example.com` (equivalent wildcard form).
+fn validate_no_proxy_entry(host: &str) -> WebDriverResult<()> {
+    let invalid = || {
+        WebDriverError::FatalError(format!("'proxy.noProxy' entry '{}' is not a valid host pattern", host))
+    };
+
+    let hostname = host.strip_prefix("*.").or_else(|| host.strip_prefix('.')).unwrap_or(host);
+    if hostname.is_empty() || hostname.contains(':') || hostname.contains('/') {
+        return Err(invalid());
+    }
+    Url::parse(&format!("proxy://{}", hostname)).map_err(|_| invalid())?;
+    Ok(())
+}
+
 #[derive(Debug, Clone, Serialize)]
 #[serde(rename_all = "lowercase")]
 pub enum AlertBehaviour {
@@ -0,0 +1,38 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::common::capabilities::desiredcapabilities::Capabilities;
+use crate::error::WebDriverResult;
+
+/// A typed builder for Firefox preferences (`moz:firefoxOptions.prefs`).
+///
+/// Without this, setting preferences means hand-assembling nested JSON
+/// under the vendor key - `FirefoxPreferences` plugs into the same
+/// `add_subkey` machinery [`Capabilities`] already uses elsewhere, so it
+/// composes with any type implementing that trait.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct FirefoxPreferences {
+    #[serde(flatten)]
+    prefs: BTreeMap<String, Value>,
+}
+
+impl FirefoxPreferences {
+    /// Create an empty set of preferences.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set an arbitrary `about:config` preference.
+    pub fn set(&mut self, name: impl Into<String>, value: impl Into<Value>) -> &mut Self {
+        self.prefs.insert(name.into(), value.into());
+        self
+    }
+
+    /// Apply these preferences to a set of capabilities under
+    /// `moz:firefoxOptions.prefs`.
+    pub fn apply<C: Capabilities>(&self, caps: &mut C) -> WebDriverResult<()> {
+        caps.add_subkey("moz:firefoxOptions", "prefs", &self.prefs)
+    }
+}
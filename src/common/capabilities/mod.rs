@@ -0,0 +1,10 @@
+pub mod chrome;
+pub mod desiredcapabilities;
+pub mod edge;
+pub mod firefox;
+pub mod firefox_prefs;
+pub mod ie;
+pub mod opera;
+pub mod safari;
+
+pub use firefox_prefs::FirefoxPreferences;
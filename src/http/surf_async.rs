@@ -1,43 +1,167 @@
 use std::fmt::Debug;
+use std::path::PathBuf;
+use std::time::Duration;
 
 use async_trait::async_trait;
+use isahc::prelude::Configurable;
+use surf::Client;
 
 use crate::http::connection_async::WebDriverHttpClientAsync;
 use crate::{
     error::{WebDriverError, WebDriverResult},
     RequestData, RequestMethod,
 };
-use isahc::prelude::Configurable;
-use std::time::Duration;
-use surf::Client;
+
+const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Builder for the `surf`/`isahc` based async HTTP client, following
+/// fantoccini's approach of making timeouts and certificate trust
+/// configurable instead of hardcoding them.
+///
+/// Which TLS implementation `isahc` links against (`native-tls` vs
+/// `rustls-tls`) is still a compile-time choice made via this crate's own
+/// `native-tls`/`rustls-tls` feature flags, which forward to the matching
+/// `isahc` feature - `ClientConfig` only controls what's configurable at
+/// runtime on top of that: timeouts and trusted root certificates.
+#[derive(Debug, Clone, Default)]
+pub struct ClientConfig {
+    root_certificate: Option<PathBuf>,
+    connect_timeout: Option<Duration>,
+    request_timeout: Option<Duration>,
+}
+
+impl ClientConfig {
+    /// Create a config with the defaults: a 30s connect timeout, a 120s
+    /// request timeout (the crate's previous fixed timeout), and the
+    /// platform's default trust store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Trust a PEM-encoded root certificate in addition to the platform's
+    /// default trust store, e.g. for talking to a grid sitting behind a
+    /// self-signed certificate.
+    ///
+    /// `isahc`'s underlying `ssl_ca_certificate` setter keeps only the most
+    /// recent certificate, so calling this more than once replaces the
+    /// previous certificate rather than accumulating both. If multiple
+    /// custom roots need to be trusted at once, concatenate them into a
+    /// single PEM file and point this at that file instead.
+    pub fn root_certificate(mut self, path: impl Into<PathBuf>) -> Self {
+        self.root_certificate = Some(path.into());
+        self
+    }
+
+    /// Set the TCP connect timeout, independent of the overall request timeout.
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Set the overall request timeout.
+    pub fn request_timeout(mut self, timeout: Duration) -> Self {
+        self.request_timeout = Some(timeout);
+        self
+    }
+
+    fn build(&self) -> WebDriverResult<Client> {
+        let mut builder = isahc::HttpClient::builder()
+            .timeout(self.request_timeout.unwrap_or(DEFAULT_REQUEST_TIMEOUT))
+            .connect_timeout(self.connect_timeout.unwrap_or(DEFAULT_CONNECT_TIMEOUT));
+
+        if let Some(path) = &self.root_certificate {
+            builder = builder.ssl_ca_certificate(isahc::config::CaCertificate::file(path));
+        }
+
+        let backing_client =
+            builder.build().map_err(|e| WebDriverError::FatalError(format!("error creating HTTP client: {}", e)))?;
+        let http_client = http_client::isahc::IsahcClient::from_client(backing_client);
+        Ok(Client::with_http_client(http_client))
+    }
+}
 
 /// Asynchronous http to the remote WebDriver server.
 #[derive(Debug)]
 pub struct SurfDriverAsync {
     url: String,
     client: Client,
+    config: ClientConfig,
+    /// Whether `client` was built from `config` by this struct (and so can
+    /// be safely rebuilt, e.g. to apply a new timeout) or handed to us
+    /// ready-made by [`SurfDriverAsync::with_client`], in which case
+    /// rebuilding it would silently discard the caller's connection
+    /// pool/backend.
+    owns_client: bool,
 }
 
-fn setup_client(timeout: Duration) -> Client {
-    let backing_client =
-        isahc::HttpClient::builder().timeout(timeout).build().expect("Error creating HTTP client");
-    let http_client = http_client::isahc::IsahcClient::from_client(backing_client);
-    Client::with_http_client(http_client)
+impl SurfDriverAsync {
+    /// Create a new client using a custom [`ClientConfig`], e.g. to pin root
+    /// certificates or set connect/request timeouts independently.
+    pub fn create_with_config(remote_server_addr: &str, config: ClientConfig) -> WebDriverResult<Self> {
+        let client = config.build()?;
+        Ok(SurfDriverAsync {
+            url: remote_server_addr.trim_end_matches('/').to_owned(),
+            client,
+            config,
+            owns_client: true,
+        })
+    }
+
+    /// Wrap an already-constructed `surf::Client`, bypassing `ClientConfig`
+    /// entirely. Useful for reusing an existing connection pool, or for
+    /// dropping in an alternative backend (e.g. a reqwest-based `surf`
+    /// client) without touching the command layer, since
+    /// `WebDriverHttpClientAsync` is the stable seam between the two.
+    ///
+    /// Because this client wasn't built from a `ClientConfig`,
+    /// [`set_request_timeout`](WebDriverHttpClientAsync::set_request_timeout)
+    /// can't rebuild it to apply a new timeout - it logs a warning and
+    /// leaves the injected client untouched instead.
+    pub fn with_client(remote_server_addr: &str, client: Client) -> Self {
+        SurfDriverAsync {
+            url: remote_server_addr.trim_end_matches('/').to_owned(),
+            client,
+            config: ClientConfig::new(),
+            owns_client: false,
+        }
+    }
 }
 
 #[async_trait]
 impl WebDriverHttpClientAsync for SurfDriverAsync {
     fn create(remote_server_addr: &str) -> WebDriverResult<Self> {
-        Ok(SurfDriverAsync {
-            url: remote_server_addr.trim_end_matches('/').to_owned(),
-            client: setup_client(Duration::from_secs(120)),
-        })
+        Self::create_with_config(remote_server_addr, ClientConfig::new())
     }
 
     fn set_request_timeout(&mut self, timeout: Duration) {
         // Currently it looks like the only way to increase the timeout is by recreating the client.
         // https://github.com/http-rs/surf/issues/267
-        self.client = setup_client(timeout);
+        if !self.owns_client {
+            // This client was handed to us via `with_client`, not built from
+            // `self.config` - rebuilding it would silently replace the caller's
+            // connection pool/backend with a fresh default one.
+            log::warn!(
+                "cannot apply new request timeout of {:?}: client was injected via with_client()",
+                timeout
+            );
+            return;
+        }
+
+        let new_config = self.config.clone().request_timeout(timeout);
+        match new_config.build() {
+            Ok(client) => {
+                self.config = new_config;
+                self.client = client;
+            }
+            Err(e) => {
+                // `WebDriverHttpClientAsync::set_request_timeout` doesn't return a
+                // Result, so a failed rebuild (e.g. a root certificate that's since
+                // gone missing) can't be propagated - log it and keep the previous
+                // client/config rather than silently discarding the new timeout.
+                log::warn!("failed to apply new request timeout of {:?}, keeping previous client: {}", timeout, e);
+            }
+        }
     }
 
     /// Execute the specified command and return the data as serde_json::Value.